@@ -6,6 +6,7 @@ type ArtistId = u64;
 type VenueId = u64;
 type ConcertId = u64;
 type TicketId = u64;
+type BidId = u64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Artist {
@@ -37,7 +38,15 @@ pub struct Concert {
     pub validated_by_venue: bool,
     pub tickets_sold: u32,
     pub revenue: u64,
+    /// Revenue contested by an open dispute and withheld from cash-out.
+    pub held: u64,
     pub cashed_out: bool,
+    /// How tickets are allocated for this concert.
+    pub allocation: AllocationMode,
+    /// Whether a lottery concert's draw has already been run.
+    pub lottery_drawn: bool,
+    /// Seconds after `date_ts` before cashed-out funds become claimable.
+    pub maturation_offset: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,14 +58,313 @@ pub struct Ticket {
     pub price_paid: u64,
     pub minted_by_artist: bool,
     pub redeem_code: Option<String>,
+    /// An open dispute is holding this purchase's funds in escrow.
+    pub disputed: bool,
+    /// The purchase was charged back; the ticket is permanently void.
+    pub void: bool,
 }
 
-#[derive(Default)]
+/// How a concert hands out its tickets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AllocationMode {
+    /// First-come, first-served via [`Ticketing::buy_ticket`].
+    #[default]
+    FirstCome,
+    /// Demand is collected, then winners are drawn by a seeded lottery.
+    Lottery,
+}
+
+/// A committed entry in a concert's fair-launch lottery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotteryEntry {
+    pub buyer: String,
+    pub deposit: u64,
+    pub entry_index: u32,
+}
+
+/// A resting sell order: a holder offering a specific ticket at `price`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ask {
+    pub ticket_id: TicketId,
+    pub seller: String,
+    pub price: u64,
+}
+
+/// A resting buy order for any ticket of a concert at up to `price`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bid {
+    pub id: BidId,
+    pub buyer: String,
+    pub price: u64,
+}
+
+/// A concert's resale book: asks ascending by price, bids descending.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub asks: Vec<Ask>,
+    pub bids: Vec<Bid>,
+}
+
+/// Who a cashed-out payout is owed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Beneficiary {
+    Artist(ArtistId),
+    Venue(VenueId),
+}
+
+/// Escrowed funds from a cashed-out concert, claimable once `now_ts` reaches
+/// `matures_at`. Only undisputed revenue is escrowed (a dispute cannot be
+/// opened after cash-out), so a chargeback never reduces a pending payout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingPayout {
+    pub concert_id: ConcertId,
+    pub beneficiary: Beneficiary,
+    pub amount: u64,
+    pub matures_at: u64,
+}
+
+/// A single mutating operation against the ledger.
+///
+/// Every state change goes through exactly one `Command`, so the whole history
+/// can be persisted as an append-only log, audited, and folded back into an
+/// identical `Ticketing` via [`Ticketing::replay`]. Variants carry the same
+/// arguments as their namesake methods; because the `next_*_id` counters are a
+/// pure function of this sequence, replay is deterministic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Command {
+    CreateArtist {
+        name: String,
+        artist_type: String,
+    },
+    UpdateArtist {
+        id: ArtistId,
+        name: String,
+        artist_type: String,
+    },
+    CreateVenue {
+        name: String,
+        capacity: u32,
+        venue_cut_bps: u16,
+        next_concert_date: Option<u64>,
+    },
+    UpdateVenue {
+        id: VenueId,
+        name: String,
+        capacity: u32,
+        venue_cut_bps: u16,
+        next_concert_date: Option<u64>,
+    },
+    CreateConcert {
+        artist_id: ArtistId,
+        venue_id: VenueId,
+        date_ts: u64,
+        ticket_price: u64,
+        total_tickets: u32,
+        allocation: AllocationMode,
+    },
+    ValidateConcertByArtist {
+        concert_id: ConcertId,
+        artist_id: ArtistId,
+    },
+    ValidateConcertByVenue {
+        concert_id: ConcertId,
+        venue_id: VenueId,
+    },
+    EmitTicket {
+        concert_id: ConcertId,
+        artist_id: ArtistId,
+        redeem_code: Option<String>,
+    },
+    BuyTicket {
+        concert_id: ConcertId,
+        buyer: String,
+        amount_paid: u64,
+    },
+    TransferTicket {
+        ticket_id: TicketId,
+        from: String,
+        to: String,
+    },
+    UseTicket {
+        ticket_id: TicketId,
+        owner: String,
+        now_ts: u64,
+    },
+    CashOut {
+        concert_id: ConcertId,
+        now_ts: u64,
+    },
+    TradeTicket {
+        ticket_id: TicketId,
+        seller: String,
+        buyer: String,
+        price: u64,
+    },
+    DistributeTicket {
+        concert_id: ConcertId,
+        artist_id: ArtistId,
+        redeem_code: String,
+    },
+    RedeemTicket {
+        code: String,
+        user: String,
+    },
+    DisputePurchase {
+        ticket_id: TicketId,
+    },
+    ResolveDispute {
+        ticket_id: TicketId,
+    },
+    Chargeback {
+        ticket_id: TicketId,
+    },
+    EnterLottery {
+        concert_id: ConcertId,
+        buyer: String,
+        deposit: u64,
+    },
+    DrawLottery {
+        concert_id: ConcertId,
+        seed: u64,
+    },
+    ClaimRefund {
+        buyer: String,
+    },
+    PlaceAsk {
+        ticket_id: TicketId,
+        seller: String,
+        price: u64,
+    },
+    PlaceBid {
+        concert_id: ConcertId,
+        buyer: String,
+        price: u64,
+    },
+    CancelAsk {
+        ticket_id: TicketId,
+    },
+    CancelBid {
+        concert_id: ConcertId,
+        bid_id: BidId,
+    },
+    SetMaturationOffset {
+        concert_id: ConcertId,
+        offset: u64,
+    },
+    Claim {
+        beneficiary: Beneficiary,
+        now_ts: u64,
+    },
+}
+
+/// What a successfully applied [`Command`] produced.
+///
+/// Creation commands report the freshly minted identifier, `RedeemTicket`
+/// reports the redeemed ticket, and `Claim`/`ClaimRefund` report the amount
+/// swept; everything else reports [`CommandOutcome::Applied`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandOutcome {
+    Artist(ArtistId),
+    Venue(VenueId),
+    Concert(ConcertId),
+    Ticket(TicketId),
+    Amount(u64),
+    Bid(BidId),
+    Applied,
+}
+
+/// Why a command (or the method wrapping it) was rejected.
+///
+/// Replaces the earlier `Option`/`bool` failures with an actionable, testable
+/// reason so callers can distinguish "sold out" from "not validated" from an
+/// arithmetic overflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TicketingError {
+    /// The referenced concert does not exist.
+    UnknownConcert,
+    /// The referenced ticket does not exist.
+    UnknownTicket,
+    /// The caller is not the current owner of the ticket.
+    NotOwner,
+    /// The caller is not the concert's artist.
+    NotConcertArtist,
+    /// The concert has not been validated by both the artist and the venue.
+    NotValidated,
+    /// No tickets remain within the concert's supply.
+    SupplyExhausted,
+    /// The ticket has already been used at the gate.
+    AlreadyUsed,
+    /// `now_ts` falls outside the concert's usage window.
+    OutsideUsageWindow,
+    /// A resale price would exceed the price originally paid.
+    PriceAboveFaceValue,
+    /// The concert has already been cashed out.
+    AlreadyCashedOut,
+    /// The concert date has not yet passed.
+    TooEarlyToCashOut,
+    /// No unclaimed ticket matches the given redeem code.
+    RedeemCodeNotFound,
+    /// The ticket is already under an open dispute.
+    AlreadyDisputed,
+    /// The ticket has no open dispute to resolve or charge back.
+    NotDisputed,
+    /// The ticket has been charged back and is permanently void.
+    TicketVoid,
+    /// Operation requires a lottery concert, but this one is first-come.
+    NotLotteryConcert,
+    /// Operation requires a first-come concert, but this one is a lottery.
+    LotteryConcert,
+    /// The lottery draw has already been run for this concert.
+    LotteryAlreadyDrawn,
+    /// A lottery deposit does not cover the ticket price.
+    DepositBelowPrice,
+    /// No matching ask/bid exists to cancel.
+    OrderNotFound,
+    /// The ticket was comped, not purchased, so it has no sale to dispute.
+    NotPurchased,
+    /// A checked arithmetic operation overflowed.
+    Overflow,
+}
+
+impl std::fmt::Display for TicketingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            TicketingError::UnknownConcert => "unknown concert",
+            TicketingError::UnknownTicket => "unknown ticket",
+            TicketingError::NotOwner => "caller does not own this ticket",
+            TicketingError::NotConcertArtist => "caller is not the concert's artist",
+            TicketingError::NotValidated => "concert is not validated by artist and venue",
+            TicketingError::SupplyExhausted => "no tickets remain in supply",
+            TicketingError::AlreadyUsed => "ticket has already been used",
+            TicketingError::OutsideUsageWindow => "outside the concert usage window",
+            TicketingError::PriceAboveFaceValue => "resale price exceeds price paid",
+            TicketingError::AlreadyCashedOut => "concert has already been cashed out",
+            TicketingError::TooEarlyToCashOut => "concert date has not yet passed",
+            TicketingError::RedeemCodeNotFound => "no ticket matches this redeem code",
+            TicketingError::AlreadyDisputed => "ticket is already under dispute",
+            TicketingError::NotDisputed => "ticket has no open dispute",
+            TicketingError::TicketVoid => "ticket has been charged back and is void",
+            TicketingError::NotLotteryConcert => "concert is not a lottery concert",
+            TicketingError::LotteryConcert => "concert allocates tickets by lottery",
+            TicketingError::LotteryAlreadyDrawn => "lottery has already been drawn",
+            TicketingError::DepositBelowPrice => "deposit does not cover the ticket price",
+            TicketingError::OrderNotFound => "no matching order to cancel",
+            TicketingError::NotPurchased => "ticket was comped, not purchased",
+            TicketingError::Overflow => "arithmetic overflow",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for TicketingError {}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Ticketing {
     next_artist_id: ArtistId,
     next_venue_id: VenueId,
     next_concert_id: ConcertId,
     next_ticket_id: TicketId,
+    next_bid_id: BidId,
 
     artists: HashMap<ArtistId, Artist>,
     venues: HashMap<VenueId, Venue>,
@@ -65,31 +373,904 @@ pub struct Ticketing {
 
     balances_artist: HashMap<ArtistId, u64>,
     balances_venue: HashMap<VenueId, u64>,
+
+    lottery_entries: HashMap<ConcertId, Vec<LotteryEntry>>,
+    refunds: HashMap<String, u64>,
+
+    books: HashMap<ConcertId, OrderBook>,
+
+    pending: Vec<PendingPayout>,
+
+    log: Vec<Command>,
+}
+
+/// A seeded splitmix64 step — a tiny, reproducible PRNG so lottery draws are
+/// auditable: the same `seed` always yields the same winners.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 impl Ticketing {
-    pub fn create_artist(&mut self, name: &str, artist_type: &str) -> ArtistId {
-        self.next_artist_id += 1;
-        let id = self.next_artist_id;
-        self.artists.insert(
-            id,
-            Artist {
+    /// Apply a command, recording it on success so the history can be replayed.
+    pub fn apply(&mut self, cmd: Command) -> Result<CommandOutcome, TicketingError> {
+        let outcome = self.dispatch(&cmd)?;
+        self.log.push(cmd);
+        Ok(outcome)
+    }
+
+    /// Rebuild a ledger by folding a command sequence in order. Because each
+    /// command reproduces the same mutations and id bumps, the result holds
+    /// the same entities, ids and balances as the state the log was captured
+    /// from (map iteration order may differ).
+    pub fn replay(commands: impl IntoIterator<Item = Command>) -> Ticketing {
+        let mut state = Ticketing::default();
+        for cmd in commands {
+            let _ = state.apply(cmd);
+        }
+        state
+    }
+
+    /// The append-only command history, for audit or persistence.
+    pub fn command_log(&self) -> &[Command] {
+        &self.log
+    }
+
+    /// Capture the full state for crash recovery; persist it with any serde
+    /// format and hand it back to [`Ticketing::restore`].
+    pub fn snapshot(&self) -> Ticketing {
+        self.clone()
+    }
+
+    /// Restore a previously captured [`snapshot`](Ticketing::snapshot).
+    pub fn restore(snapshot: Ticketing) -> Ticketing {
+        snapshot
+    }
+
+    fn dispatch(&mut self, cmd: &Command) -> Result<CommandOutcome, TicketingError> {
+        match cmd {
+            Command::CreateArtist { name, artist_type } => {
+                self.next_artist_id += 1;
+                let id = self.next_artist_id;
+                self.artists.insert(
+                    id,
+                    Artist {
+                        id,
+                        name: name.clone(),
+                        artist_type: artist_type.clone(),
+                        total_tickets_sold: 0,
+                    },
+                );
+                Ok(CommandOutcome::Artist(id))
+            }
+            Command::UpdateArtist {
                 id,
-                name: name.to_string(),
-                artist_type: artist_type.to_string(),
-                total_tickets_sold: 0,
-            },
-        );
-        id
+                name,
+                artist_type,
+            } => {
+                if let Some(artist) = self.artists.get_mut(id) {
+                    artist.name = name.clone();
+                    artist.artist_type = artist_type.clone();
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::CreateVenue {
+                name,
+                capacity,
+                venue_cut_bps,
+                next_concert_date,
+            } => {
+                self.next_venue_id += 1;
+                let id = self.next_venue_id;
+                self.venues.insert(
+                    id,
+                    Venue {
+                        id,
+                        name: name.clone(),
+                        capacity: *capacity,
+                        venue_cut_bps: *venue_cut_bps,
+                        next_concert_date: *next_concert_date,
+                    },
+                );
+                Ok(CommandOutcome::Venue(id))
+            }
+            Command::UpdateVenue {
+                id,
+                name,
+                capacity,
+                venue_cut_bps,
+                next_concert_date,
+            } => {
+                if let Some(venue) = self.venues.get_mut(id) {
+                    venue.name = name.clone();
+                    venue.capacity = *capacity;
+                    venue.venue_cut_bps = *venue_cut_bps;
+                    venue.next_concert_date = *next_concert_date;
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::CreateConcert {
+                artist_id,
+                venue_id,
+                date_ts,
+                ticket_price,
+                total_tickets,
+                allocation,
+            } => {
+                self.next_concert_id += 1;
+                let id = self.next_concert_id;
+                self.concerts.insert(
+                    id,
+                    Concert {
+                        id,
+                        artist_id: *artist_id,
+                        venue_id: *venue_id,
+                        date_ts: *date_ts,
+                        ticket_price: *ticket_price,
+                        total_tickets: *total_tickets,
+                        tickets_issued: 0,
+                        validated_by_artist: false,
+                        validated_by_venue: false,
+                        tickets_sold: 0,
+                        revenue: 0,
+                        held: 0,
+                        cashed_out: false,
+                        allocation: *allocation,
+                        lottery_drawn: false,
+                        maturation_offset: 0,
+                    },
+                );
+                Ok(CommandOutcome::Concert(id))
+            }
+            Command::ValidateConcertByArtist {
+                concert_id,
+                artist_id,
+            } => {
+                if let Some(concert) = self.concerts.get_mut(concert_id) {
+                    if concert.artist_id == *artist_id {
+                        concert.validated_by_artist = true;
+                    }
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::ValidateConcertByVenue {
+                concert_id,
+                venue_id,
+            } => {
+                if let Some(concert) = self.concerts.get_mut(concert_id) {
+                    if concert.venue_id == *venue_id {
+                        concert.validated_by_venue = true;
+                    }
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::EmitTicket {
+                concert_id,
+                artist_id,
+                redeem_code,
+            } => {
+                let concert = self
+                    .concerts
+                    .get(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.artist_id != *artist_id {
+                    return Err(TicketingError::NotConcertArtist);
+                }
+                if concert.allocation == AllocationMode::Lottery {
+                    return Err(TicketingError::LotteryConcert);
+                }
+                if !concert.validated_by_artist || !concert.validated_by_venue {
+                    return Err(TicketingError::NotValidated);
+                }
+                if concert.tickets_issued >= concert.total_tickets {
+                    return Err(TicketingError::SupplyExhausted);
+                }
+
+                self.next_ticket_id += 1;
+                let ticket_id = self.next_ticket_id;
+                self.tickets.insert(
+                    ticket_id,
+                    Ticket {
+                        id: ticket_id,
+                        concert_id: *concert_id,
+                        owner: Some(format!("artist:{artist_id}")),
+                        used: false,
+                        price_paid: 0,
+                        minted_by_artist: true,
+                        redeem_code: redeem_code.clone(),
+                        disputed: false,
+                        void: false,
+                    },
+                );
+                if let Some(concert) = self.concerts.get_mut(concert_id) {
+                    concert.tickets_issued = concert
+                        .tickets_issued
+                        .checked_add(1)
+                        .ok_or(TicketingError::Overflow)?;
+                }
+                Ok(CommandOutcome::Ticket(ticket_id))
+            }
+            Command::BuyTicket {
+                concert_id,
+                buyer,
+                amount_paid,
+            } => {
+                let concert = self
+                    .concerts
+                    .get(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.allocation == AllocationMode::Lottery {
+                    return Err(TicketingError::LotteryConcert);
+                }
+                if !concert.validated_by_artist || !concert.validated_by_venue {
+                    return Err(TicketingError::NotValidated);
+                }
+                if concert.tickets_issued >= concert.total_tickets {
+                    return Err(TicketingError::SupplyExhausted);
+                }
+                // Compute every updated counter up front so an overflow leaves
+                // the ledger untouched rather than half-mutated.
+                let new_sold = concert
+                    .tickets_sold
+                    .checked_add(1)
+                    .ok_or(TicketingError::Overflow)?;
+                let new_issued = concert
+                    .tickets_issued
+                    .checked_add(1)
+                    .ok_or(TicketingError::Overflow)?;
+                let new_revenue = concert
+                    .revenue
+                    .checked_add(*amount_paid)
+                    .ok_or(TicketingError::Overflow)?;
+                let artist_id = concert.artist_id;
+                let new_total = match self.artists.get(&artist_id) {
+                    Some(artist) => Some(
+                        artist
+                            .total_tickets_sold
+                            .checked_add(1)
+                            .ok_or(TicketingError::Overflow)?,
+                    ),
+                    None => None,
+                };
+                if let Some(concert) = self.concerts.get_mut(concert_id) {
+                    concert.tickets_sold = new_sold;
+                    concert.tickets_issued = new_issued;
+                    concert.revenue = new_revenue;
+                }
+
+                self.next_ticket_id += 1;
+                let ticket_id = self.next_ticket_id;
+                self.tickets.insert(
+                    ticket_id,
+                    Ticket {
+                        id: ticket_id,
+                        concert_id: *concert_id,
+                        owner: Some(buyer.clone()),
+                        used: false,
+                        price_paid: *amount_paid,
+                        minted_by_artist: false,
+                        redeem_code: None,
+                        disputed: false,
+                        void: false,
+                    },
+                );
+                if let (Some(artist), Some(new_total)) =
+                    (self.artists.get_mut(&artist_id), new_total)
+                {
+                    artist.total_tickets_sold = new_total;
+                }
+                Ok(CommandOutcome::Ticket(ticket_id))
+            }
+            Command::TransferTicket { ticket_id, from, to } => {
+                let ticket = self
+                    .tickets
+                    .get_mut(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if ticket.owner.as_deref() != Some(from.as_str()) {
+                    return Err(TicketingError::NotOwner);
+                }
+                if ticket.used {
+                    return Err(TicketingError::AlreadyUsed);
+                }
+                ticket.owner = Some(to.clone());
+                Ok(CommandOutcome::Applied)
+            }
+            Command::UseTicket {
+                ticket_id,
+                owner,
+                now_ts,
+            } => {
+                let concert_id = match self.tickets.get(ticket_id) {
+                    Some(ticket) if ticket.owner.as_deref() != Some(owner.as_str()) => {
+                        return Err(TicketingError::NotOwner);
+                    }
+                    Some(ticket) if ticket.void => return Err(TicketingError::TicketVoid),
+                    Some(ticket) if ticket.disputed => return Err(TicketingError::AlreadyDisputed),
+                    Some(ticket) if ticket.used => return Err(TicketingError::AlreadyUsed),
+                    Some(ticket) => ticket.concert_id,
+                    None => return Err(TicketingError::UnknownTicket),
+                };
+                let concert = self
+                    .concerts
+                    .get(&concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if !(concert.validated_by_artist && concert.validated_by_venue) {
+                    return Err(TicketingError::NotValidated);
+                }
+                let window_start = concert.date_ts.saturating_sub(86_400);
+                if *now_ts < window_start || *now_ts > concert.date_ts {
+                    return Err(TicketingError::OutsideUsageWindow);
+                }
+                if let Some(ticket) = self.tickets.get_mut(ticket_id) {
+                    ticket.used = true;
+                    return Ok(CommandOutcome::Applied);
+                }
+                Err(TicketingError::UnknownTicket)
+            }
+            Command::CashOut { concert_id, now_ts } => {
+                let (artist_id, venue_id, revenue, venue_cut_bps, cashed_out, date_ts, offset) =
+                    match self.concerts.get(concert_id) {
+                        Some(c) => (
+                            c.artist_id,
+                            c.venue_id,
+                            c.revenue,
+                            self.venues
+                                .get(&c.venue_id)
+                                .map(|v| v.venue_cut_bps)
+                                .unwrap_or(0),
+                            c.cashed_out,
+                            c.date_ts,
+                            c.maturation_offset,
+                        ),
+                        None => return Err(TicketingError::UnknownConcert),
+                    };
+                if cashed_out {
+                    return Err(TicketingError::AlreadyCashedOut);
+                }
+                if *now_ts < date_ts {
+                    return Err(TicketingError::TooEarlyToCashOut);
+                }
+                // Widen to u128 so `revenue * bps` cannot truncate before the
+                // divide, then cap at `revenue` (bps may exceed 10_000) and give
+                // the rounding remainder to the artist so the split is exact.
+                let venue_cut =
+                    ((revenue as u128 * venue_cut_bps as u128) / 10_000) as u64;
+                let venue_cut = venue_cut.min(revenue);
+                let artist_cut = revenue - venue_cut;
+                // Escrow the split rather than crediting it outright; it settles
+                // to claimable balances only after the maturation window.
+                let matures_at = date_ts
+                    .checked_add(offset)
+                    .ok_or(TicketingError::Overflow)?;
+                if artist_cut > 0 {
+                    self.pending.push(PendingPayout {
+                        concert_id: *concert_id,
+                        beneficiary: Beneficiary::Artist(artist_id),
+                        amount: artist_cut,
+                        matures_at,
+                    });
+                }
+                if venue_cut > 0 {
+                    self.pending.push(PendingPayout {
+                        concert_id: *concert_id,
+                        beneficiary: Beneficiary::Venue(venue_id),
+                        amount: venue_cut,
+                        matures_at,
+                    });
+                }
+                if let Some(c) = self.concerts.get_mut(concert_id) {
+                    c.cashed_out = true;
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::TradeTicket {
+                ticket_id,
+                seller,
+                buyer,
+                price,
+            } => {
+                let ticket = self
+                    .tickets
+                    .get_mut(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if ticket.owner.as_deref() != Some(seller.as_str()) {
+                    return Err(TicketingError::NotOwner);
+                }
+                if ticket.void {
+                    return Err(TicketingError::TicketVoid);
+                }
+                if ticket.disputed {
+                    return Err(TicketingError::AlreadyDisputed);
+                }
+                if ticket.used {
+                    return Err(TicketingError::AlreadyUsed);
+                }
+                if *price > ticket.price_paid {
+                    return Err(TicketingError::PriceAboveFaceValue);
+                }
+                ticket.owner = Some(buyer.clone());
+                ticket.price_paid = *price;
+                Ok(CommandOutcome::Applied)
+            }
+            Command::DistributeTicket {
+                concert_id,
+                artist_id,
+                redeem_code,
+            } => {
+                let concert = self
+                    .concerts
+                    .get_mut(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.artist_id != *artist_id {
+                    return Err(TicketingError::NotConcertArtist);
+                }
+                if concert.allocation == AllocationMode::Lottery {
+                    return Err(TicketingError::LotteryConcert);
+                }
+                if !concert.validated_by_artist || !concert.validated_by_venue {
+                    return Err(TicketingError::NotValidated);
+                }
+                if concert.tickets_issued >= concert.total_tickets {
+                    return Err(TicketingError::SupplyExhausted);
+                }
+                concert.tickets_issued = concert
+                    .tickets_issued
+                    .checked_add(1)
+                    .ok_or(TicketingError::Overflow)?;
+
+                self.next_ticket_id += 1;
+                let ticket_id = self.next_ticket_id;
+                self.tickets.insert(
+                    ticket_id,
+                    Ticket {
+                        id: ticket_id,
+                        concert_id: *concert_id,
+                        owner: None,
+                        used: false,
+                        price_paid: 0,
+                        minted_by_artist: true,
+                        redeem_code: Some(redeem_code.clone()),
+                        disputed: false,
+                        void: false,
+                    },
+                );
+                Ok(CommandOutcome::Ticket(ticket_id))
+            }
+            Command::RedeemTicket { code, user } => {
+                // Several unredeemed tickets may share a code, so pick the
+                // lowest matching `TicketId`; `HashMap` iteration order is
+                // randomized per instance and would otherwise make `replay`
+                // assign the owner to a different ticket.
+                let target = self
+                    .tickets
+                    .iter()
+                    .filter(|(_, t)| {
+                        t.owner.is_none() && t.redeem_code.as_deref() == Some(code.as_str())
+                    })
+                    .map(|(id, _)| *id)
+                    .min();
+                match target {
+                    Some(id) => {
+                        if let Some(ticket) = self.tickets.get_mut(&id) {
+                            ticket.owner = Some(user.clone());
+                        }
+                        Ok(CommandOutcome::Ticket(id))
+                    }
+                    None => Err(TicketingError::RedeemCodeNotFound),
+                }
+            }
+            Command::DisputePurchase { ticket_id } => {
+                let ticket = self
+                    .tickets
+                    .get(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if ticket.void {
+                    return Err(TicketingError::TicketVoid);
+                }
+                if ticket.minted_by_artist {
+                    // Comp tickets never counted toward a sale, so there is
+                    // nothing to reverse and disputing one would corrupt the
+                    // sold counters.
+                    return Err(TicketingError::NotPurchased);
+                }
+                if ticket.disputed {
+                    return Err(TicketingError::AlreadyDisputed);
+                }
+                if ticket.used {
+                    return Err(TicketingError::AlreadyUsed);
+                }
+                let concert_id = ticket.concert_id;
+                let amount = ticket.price_paid;
+                let concert = self
+                    .concerts
+                    .get_mut(&concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.cashed_out {
+                    return Err(TicketingError::AlreadyCashedOut);
+                }
+                // Available revenue always covers the price paid (resale only
+                // lowers a ticket's `price_paid`), so the subtraction is safe.
+                concert.revenue = concert
+                    .revenue
+                    .checked_sub(amount)
+                    .ok_or(TicketingError::Overflow)?;
+                concert.held = concert
+                    .held
+                    .checked_add(amount)
+                    .ok_or(TicketingError::Overflow)?;
+                if let Some(ticket) = self.tickets.get_mut(ticket_id) {
+                    ticket.disputed = true;
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::ResolveDispute { ticket_id } => {
+                let ticket = self
+                    .tickets
+                    .get(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if !ticket.disputed {
+                    return Err(TicketingError::NotDisputed);
+                }
+                let concert_id = ticket.concert_id;
+                let amount = ticket.price_paid;
+                let concert = self
+                    .concerts
+                    .get_mut(&concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.cashed_out {
+                    // The concert can never be cashed out again, so returning
+                    // the held funds to `revenue` would strand them; resolving
+                    // must happen within the cash-out window.
+                    return Err(TicketingError::AlreadyCashedOut);
+                }
+                concert.held = concert
+                    .held
+                    .checked_sub(amount)
+                    .ok_or(TicketingError::Overflow)?;
+                concert.revenue = concert
+                    .revenue
+                    .checked_add(amount)
+                    .ok_or(TicketingError::Overflow)?;
+                if let Some(ticket) = self.tickets.get_mut(ticket_id) {
+                    ticket.disputed = false;
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::Chargeback { ticket_id } => {
+                let ticket = self
+                    .tickets
+                    .get(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if ticket.minted_by_artist {
+                    // Comp tickets are never sold, so they must not touch the
+                    // sold counters on the way out.
+                    return Err(TicketingError::NotPurchased);
+                }
+                if !ticket.disputed {
+                    return Err(TicketingError::NotDisputed);
+                }
+                let concert_id = ticket.concert_id;
+                let amount = ticket.price_paid;
+                let concert = self
+                    .concerts
+                    .get_mut(&concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                // A dispute can only be opened before cash-out, so the contested
+                // funds always sit in `held` and were never escrowed into the
+                // pending payout, which belongs to this concert's other,
+                // undisputed tickets. Reverse the funds from `held` alone and
+                // leave every other ticket's pending payout intact. The
+                // `checked_sub` is the guard — if this ticket's own money had
+                // already been paid out it would not be in `held` and the
+                // subtraction would fail.
+                concert.held = concert
+                    .held
+                    .checked_sub(amount)
+                    .ok_or(TicketingError::Overflow)?;
+                concert.tickets_sold = concert.tickets_sold.saturating_sub(1);
+                let artist_id = concert.artist_id;
+                if let Some(artist) = self.artists.get_mut(&artist_id) {
+                    artist.total_tickets_sold = artist.total_tickets_sold.saturating_sub(1);
+                }
+                if let Some(ticket) = self.tickets.get_mut(ticket_id) {
+                    ticket.disputed = false;
+                    ticket.void = true;
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::EnterLottery {
+                concert_id,
+                buyer,
+                deposit,
+            } => {
+                let concert = self
+                    .concerts
+                    .get(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.allocation != AllocationMode::Lottery {
+                    return Err(TicketingError::NotLotteryConcert);
+                }
+                if concert.lottery_drawn {
+                    return Err(TicketingError::LotteryAlreadyDrawn);
+                }
+                if *deposit < concert.ticket_price {
+                    return Err(TicketingError::DepositBelowPrice);
+                }
+                let entries = self.lottery_entries.entry(*concert_id).or_default();
+                let entry_index = entries.len() as u32;
+                entries.push(LotteryEntry {
+                    buyer: buyer.clone(),
+                    deposit: *deposit,
+                    entry_index,
+                });
+                Ok(CommandOutcome::Applied)
+            }
+            Command::DrawLottery { concert_id, seed } => {
+                let concert = self
+                    .concerts
+                    .get(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                if concert.allocation != AllocationMode::Lottery {
+                    return Err(TicketingError::NotLotteryConcert);
+                }
+                if concert.lottery_drawn {
+                    return Err(TicketingError::LotteryAlreadyDrawn);
+                }
+                let ticket_price = concert.ticket_price;
+                let artist_id = concert.artist_id;
+                // Winners draw against the capacity still unissued, not the
+                // gross total, so any tickets already placed are subtracted.
+                let supply = concert
+                    .total_tickets
+                    .saturating_sub(concert.tickets_issued) as usize;
+                let entries = self.lottery_entries.remove(concert_id).unwrap_or_default();
+
+                // Oversubscribed draws shuffle the entry order with the seeded
+                // PRNG and take the first `supply` as winners; otherwise every
+                // entry wins. Losers (and winner overpayment) flow to refunds.
+                let mut order: Vec<usize> = (0..entries.len()).collect();
+                if entries.len() > supply {
+                    let mut state = *seed;
+                    for i in (1..order.len()).rev() {
+                        let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+                        order.swap(i, j);
+                    }
+                }
+                let mut won = vec![false; entries.len()];
+                let winners = order.len().min(supply);
+                for &idx in order.iter().take(winners) {
+                    won[idx] = true;
+                }
+
+                // Issue tickets in ascending entry order for stable ids.
+                for (idx, entry) in entries.iter().enumerate() {
+                    if won[idx] {
+                        self.next_ticket_id += 1;
+                        let ticket_id = self.next_ticket_id;
+                        self.tickets.insert(
+                            ticket_id,
+                            Ticket {
+                                id: ticket_id,
+                                concert_id: *concert_id,
+                                owner: Some(entry.buyer.clone()),
+                                used: false,
+                                price_paid: ticket_price,
+                                minted_by_artist: false,
+                                redeem_code: None,
+                                disputed: false,
+                                void: false,
+                            },
+                        );
+                        let overpayment = entry.deposit.saturating_sub(ticket_price);
+                        if overpayment > 0 {
+                            *self.refunds.entry(entry.buyer.clone()).or_default() += overpayment;
+                        }
+                    } else {
+                        *self.refunds.entry(entry.buyer.clone()).or_default() += entry.deposit;
+                    }
+                }
+
+                if let Some(concert) = self.concerts.get_mut(concert_id) {
+                    concert.tickets_sold = concert.tickets_sold.saturating_add(winners as u32);
+                    concert.tickets_issued = concert.tickets_issued.saturating_add(winners as u32);
+                    concert.revenue = concert
+                        .revenue
+                        .saturating_add(ticket_price.saturating_mul(winners as u64));
+                    concert.lottery_drawn = true;
+                }
+                if let Some(artist) = self.artists.get_mut(&artist_id) {
+                    artist.total_tickets_sold =
+                        artist.total_tickets_sold.saturating_add(winners as u32);
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::ClaimRefund { buyer } => {
+                let amount = self.refunds.remove(buyer).unwrap_or(0);
+                Ok(CommandOutcome::Amount(amount))
+            }
+            Command::PlaceAsk {
+                ticket_id,
+                seller,
+                price,
+            } => {
+                let ticket = self
+                    .tickets
+                    .get(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?;
+                if ticket.void {
+                    return Err(TicketingError::TicketVoid);
+                }
+                if ticket.disputed {
+                    return Err(TicketingError::AlreadyDisputed);
+                }
+                if ticket.used {
+                    return Err(TicketingError::AlreadyUsed);
+                }
+                if ticket.owner.as_deref() != Some(seller.as_str()) {
+                    return Err(TicketingError::NotOwner);
+                }
+                if *price > ticket.price_paid {
+                    return Err(TicketingError::PriceAboveFaceValue);
+                }
+                let concert_id = ticket.concert_id;
+                let book = self.books.entry(concert_id).or_default();
+                // One resting ask per ticket; a re-list replaces the old one.
+                book.asks.retain(|a| a.ticket_id != *ticket_id);
+                book.asks.push(Ask {
+                    ticket_id: *ticket_id,
+                    seller: seller.clone(),
+                    price: *price,
+                });
+                book.asks.sort_by_key(|a| (a.price, a.ticket_id));
+                self.match_orders(concert_id);
+                Ok(CommandOutcome::Applied)
+            }
+            Command::PlaceBid {
+                concert_id,
+                buyer,
+                price,
+            } => {
+                if !self.concerts.contains_key(concert_id) {
+                    return Err(TicketingError::UnknownConcert);
+                }
+                self.next_bid_id += 1;
+                let bid_id = self.next_bid_id;
+                let book = self.books.entry(*concert_id).or_default();
+                book.bids.push(Bid {
+                    id: bid_id,
+                    buyer: buyer.clone(),
+                    price: *price,
+                });
+                book.bids
+                    .sort_by(|x, y| y.price.cmp(&x.price).then(x.id.cmp(&y.id)));
+                self.match_orders(*concert_id);
+                Ok(CommandOutcome::Bid(bid_id))
+            }
+            Command::CancelAsk { ticket_id } => {
+                let concert_id = self
+                    .tickets
+                    .get(ticket_id)
+                    .ok_or(TicketingError::UnknownTicket)?
+                    .concert_id;
+                let book = self
+                    .books
+                    .get_mut(&concert_id)
+                    .ok_or(TicketingError::OrderNotFound)?;
+                let before = book.asks.len();
+                book.asks.retain(|a| a.ticket_id != *ticket_id);
+                if book.asks.len() == before {
+                    return Err(TicketingError::OrderNotFound);
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::CancelBid { concert_id, bid_id } => {
+                let book = self
+                    .books
+                    .get_mut(concert_id)
+                    .ok_or(TicketingError::OrderNotFound)?;
+                let before = book.bids.len();
+                book.bids.retain(|b| b.id != *bid_id);
+                if book.bids.len() == before {
+                    return Err(TicketingError::OrderNotFound);
+                }
+                Ok(CommandOutcome::Applied)
+            }
+            Command::SetMaturationOffset { concert_id, offset } => {
+                let concert = self
+                    .concerts
+                    .get_mut(concert_id)
+                    .ok_or(TicketingError::UnknownConcert)?;
+                concert.maturation_offset = *offset;
+                Ok(CommandOutcome::Applied)
+            }
+            Command::Claim {
+                beneficiary,
+                now_ts,
+            } => {
+                let mut total: u64 = 0;
+                let mut i = 0;
+                while i < self.pending.len() {
+                    let payout = &self.pending[i];
+                    if payout.beneficiary == *beneficiary && payout.matures_at <= *now_ts {
+                        total = total
+                            .checked_add(payout.amount)
+                            .ok_or(TicketingError::Overflow)?;
+                        self.pending.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+                let balance = match beneficiary {
+                    Beneficiary::Artist(id) => self.balances_artist.entry(*id).or_default(),
+                    Beneficiary::Venue(id) => self.balances_venue.entry(*id).or_default(),
+                };
+                *balance = balance.checked_add(total).ok_or(TicketingError::Overflow)?;
+                Ok(CommandOutcome::Amount(total))
+            }
+        }
     }
 
-    pub fn update_artist(&mut self, id: ArtistId, name: &str, artist_type: &str) {
-        if let Some(artist) = self.artists.get_mut(&id) {
-            artist.name = name.to_string();
-            artist.artist_type = artist_type.to_string();
+    /// Cross resting orders: while the lowest ask is at or below the highest
+    /// bid, settle that ticket at the ask price. Since each `TicketId` is
+    /// unique there is no partial fill — a match fully settles one bid.
+    fn match_orders(&mut self, concert_id: ConcertId) {
+        loop {
+            let (ask, bid) = match self.books.get(&concert_id) {
+                Some(book) => match (book.asks.first(), book.bids.first()) {
+                    (Some(a), Some(b)) if a.price <= b.price => (a.clone(), b.clone()),
+                    _ => return,
+                },
+                None => return,
+            };
+            // The ask may have gone stale since it was placed (ticket used,
+            // disputed, transferred away, or re-priced below the ask).
+            let valid = match self.tickets.get(&ask.ticket_id) {
+                Some(t) => {
+                    !t.used
+                        && !t.void
+                        && !t.disputed
+                        && t.owner.as_deref() == Some(ask.seller.as_str())
+                        && ask.price <= t.price_paid
+                }
+                None => false,
+            };
+            if let Some(book) = self.books.get_mut(&concert_id) {
+                book.asks.remove(0);
+            }
+            if !valid {
+                continue;
+            }
+            if let Some(book) = self.books.get_mut(&concert_id) {
+                book.bids.remove(0);
+            }
+            if let Some(ticket) = self.tickets.get_mut(&ask.ticket_id) {
+                ticket.owner = Some(bid.buyer.clone());
+                ticket.price_paid = ask.price;
+            }
+        }
+    }
+
+    pub fn create_artist(&mut self, name: &str, artist_type: &str) -> ArtistId {
+        match self.apply(Command::CreateArtist {
+            name: name.to_string(),
+            artist_type: artist_type.to_string(),
+        }) {
+            Ok(CommandOutcome::Artist(id)) => id,
+            _ => unreachable!("create_artist always succeeds"),
         }
     }
 
+    pub fn update_artist(&mut self, id: ArtistId, name: &str, artist_type: &str) {
+        let _ = self.apply(Command::UpdateArtist {
+            id,
+            name: name.to_string(),
+            artist_type: artist_type.to_string(),
+        });
+    }
+
     pub fn create_venue(
         &mut self,
         name: &str,
@@ -97,19 +1278,15 @@ impl Ticketing {
         venue_cut_bps: u16,
         next_concert_date: Option<u64>,
     ) -> VenueId {
-        self.next_venue_id += 1;
-        let id = self.next_venue_id;
-        self.venues.insert(
-            id,
-            Venue {
-                id,
-                name: name.to_string(),
-                capacity,
-                venue_cut_bps,
-                next_concert_date,
-            },
-        );
-        id
+        match self.apply(Command::CreateVenue {
+            name: name.to_string(),
+            capacity,
+            venue_cut_bps,
+            next_concert_date,
+        }) {
+            Ok(CommandOutcome::Venue(id)) => id,
+            _ => unreachable!("create_venue always succeeds"),
+        }
     }
 
     pub fn update_venue(
@@ -120,12 +1297,13 @@ impl Ticketing {
         venue_cut_bps: u16,
         next_concert_date: Option<u64>,
     ) {
-        if let Some(venue) = self.venues.get_mut(&id) {
-            venue.name = name.to_string();
-            venue.capacity = capacity;
-            venue.venue_cut_bps = venue_cut_bps;
-            venue.next_concert_date = next_concert_date;
-        }
+        let _ = self.apply(Command::UpdateVenue {
+            id,
+            name: name.to_string(),
+            capacity,
+            venue_cut_bps,
+            next_concert_date,
+        });
     }
 
     pub fn create_concert(
@@ -136,42 +1314,54 @@ impl Ticketing {
         ticket_price: u64,
         total_tickets: u32,
     ) -> ConcertId {
-        self.next_concert_id += 1;
-        let id = self.next_concert_id;
-        self.concerts.insert(
-            id,
-            Concert {
-                id,
-                artist_id,
-                venue_id,
-                date_ts,
-                ticket_price,
-                total_tickets,
-                tickets_issued: 0,
-                validated_by_artist: false,
-                validated_by_venue: false,
-                tickets_sold: 0,
-                revenue: 0,
-                cashed_out: false,
-            },
-        );
-        id
+        self.create_concert_with_allocation(
+            artist_id,
+            venue_id,
+            date_ts,
+            ticket_price,
+            total_tickets,
+            AllocationMode::FirstCome,
+        )
     }
 
-    pub fn validate_concert_by_artist(&mut self, concert_id: ConcertId, artist_id: ArtistId) {
-        if let Some(concert) = self.concerts.get_mut(&concert_id) {
-            if concert.artist_id == artist_id {
-                concert.validated_by_artist = true;
-            }
+    /// Create a concert with an explicit allocation mode. `FirstCome` behaves
+    /// exactly like [`create_concert`](Ticketing::create_concert); `Lottery`
+    /// disables `buy_ticket` in favour of [`enter_lottery`](Ticketing::enter_lottery)
+    /// and [`draw_lottery`](Ticketing::draw_lottery).
+    pub fn create_concert_with_allocation(
+        &mut self,
+        artist_id: ArtistId,
+        venue_id: VenueId,
+        date_ts: u64,
+        ticket_price: u64,
+        total_tickets: u32,
+        allocation: AllocationMode,
+    ) -> ConcertId {
+        match self.apply(Command::CreateConcert {
+            artist_id,
+            venue_id,
+            date_ts,
+            ticket_price,
+            total_tickets,
+            allocation,
+        }) {
+            Ok(CommandOutcome::Concert(id)) => id,
+            _ => unreachable!("create_concert always succeeds"),
         }
     }
 
+    pub fn validate_concert_by_artist(&mut self, concert_id: ConcertId, artist_id: ArtistId) {
+        let _ = self.apply(Command::ValidateConcertByArtist {
+            concert_id,
+            artist_id,
+        });
+    }
+
     pub fn validate_concert_by_venue(&mut self, concert_id: ConcertId, venue_id: VenueId) {
-        if let Some(concert) = self.concerts.get_mut(&concert_id) {
-            if concert.venue_id == venue_id {
-                concert.validated_by_venue = true;
-            }
-        }
+        let _ = self.apply(Command::ValidateConcertByVenue {
+            concert_id,
+            venue_id,
+        });
     }
 
     pub fn emit_ticket(
@@ -179,115 +1369,64 @@ impl Ticketing {
         concert_id: ConcertId,
         artist_id: ArtistId,
         redeem_code: Option<String>,
-    ) -> Option<TicketId> {
-        let concert = self.concerts.get(&concert_id)?;
-        if concert.artist_id != artist_id || !concert.validated_by_artist || !concert.validated_by_venue {
-            return None;
-        }
-        if concert.tickets_issued >= concert.total_tickets {
-            return None;
-        }
-
-        self.next_ticket_id += 1;
-        let ticket_id = self.next_ticket_id;
-        self.tickets.insert(
-            ticket_id,
-            Ticket {
-                id: ticket_id,
-                concert_id,
-                owner: Some(format!("artist:{artist_id}")),
-                used: false,
-                price_paid: 0,
-                minted_by_artist: true,
-                redeem_code,
-            },
-        );
-        if let Some(concert) = self.concerts.get_mut(&concert_id) {
-            concert.tickets_issued += 1;
+    ) -> Result<TicketId, TicketingError> {
+        match self.apply(Command::EmitTicket {
+            concert_id,
+            artist_id,
+            redeem_code,
+        })? {
+            CommandOutcome::Ticket(id) => Ok(id),
+            _ => unreachable!("emit_ticket yields a ticket on success"),
         }
-        Some(ticket_id)
     }
 
-    pub fn buy_ticket(&mut self, concert_id: ConcertId, buyer: &str, amount_paid: u64) -> Option<TicketId> {
-        let concert = self.concerts.get_mut(&concert_id)?;
-        if !concert.validated_by_artist || !concert.validated_by_venue {
-            return None;
-        }
-        if concert.tickets_issued >= concert.total_tickets {
-            return None;
-        }
-        concert.tickets_sold = concert.tickets_sold.saturating_add(1);
-        concert.tickets_issued = concert.tickets_issued.saturating_add(1);
-        concert.revenue = concert.revenue.saturating_add(amount_paid);
-
-        self.next_ticket_id += 1;
-        let ticket_id = self.next_ticket_id;
-        self.tickets.insert(
-            ticket_id,
-            Ticket {
-                id: ticket_id,
-                concert_id,
-                owner: Some(buyer.to_string()),
-                used: false,
-                price_paid: amount_paid,
-                minted_by_artist: false,
-                redeem_code: None,
-            },
-        );
-        if let Some(artist) = self.artists.get_mut(&concert.artist_id) {
-            artist.total_tickets_sold += 1;
+    pub fn buy_ticket(
+        &mut self,
+        concert_id: ConcertId,
+        buyer: &str,
+        amount_paid: u64,
+    ) -> Result<TicketId, TicketingError> {
+        match self.apply(Command::BuyTicket {
+            concert_id,
+            buyer: buyer.to_string(),
+            amount_paid,
+        })? {
+            CommandOutcome::Ticket(id) => Ok(id),
+            _ => unreachable!("buy_ticket yields a ticket on success"),
         }
-        Some(ticket_id)
     }
 
-    pub fn transfer_ticket(&mut self, ticket_id: TicketId, from: &str, to: &str) -> bool {
-        if let Some(ticket) = self.tickets.get_mut(&ticket_id) {
-            if ticket.owner.as_deref() == Some(from) && !ticket.used {
-                ticket.owner = Some(to.to_string());
-                return true;
-            }
-        }
-        false
+    pub fn transfer_ticket(
+        &mut self,
+        ticket_id: TicketId,
+        from: &str,
+        to: &str,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::TransferTicket {
+            ticket_id,
+            from: from.to_string(),
+            to: to.to_string(),
+        })?;
+        Ok(())
     }
 
-    pub fn use_ticket(&mut self, ticket_id: TicketId, owner: &str, now_ts: u64) -> bool {
-        let concert_id = match self.tickets.get(&ticket_id) {
-            Some(ticket) if ticket.owner.as_deref() == Some(owner) && !ticket.used => ticket.concert_id,
-            _ => return false,
-        };
-        let concert = match self.concerts.get(&concert_id) {
-            Some(c) => c,
-            None => return false,
-        };
-        if !(concert.validated_by_artist && concert.validated_by_venue) {
-            return false;
-        }
-        let window_start = concert.date_ts.saturating_sub(86_400);
-        if now_ts >= window_start && now_ts <= concert.date_ts {
-            if let Some(ticket) = self.tickets.get_mut(&ticket_id) {
-                ticket.used = true;
-                return true;
-            }
-        }
-        false
+    pub fn use_ticket(
+        &mut self,
+        ticket_id: TicketId,
+        owner: &str,
+        now_ts: u64,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::UseTicket {
+            ticket_id,
+            owner: owner.to_string(),
+            now_ts,
+        })?;
+        Ok(())
     }
 
-    pub fn cash_out(&mut self, concert_id: ConcertId, now_ts: u64) -> bool {
-        let (artist_id, venue_id, revenue, venue_cut_bps, cashed_out, date_ts) = match self.concerts.get(&concert_id) {
-            Some(c) => (c.artist_id, c.venue_id, c.revenue, self.venues.get(&c.venue_id).map(|v| v.venue_cut_bps).unwrap_or(0), c.cashed_out, c.date_ts),
-            None => return false,
-        };
-        if cashed_out || now_ts < date_ts {
-            return false;
-        }
-        let venue_cut = revenue * venue_cut_bps as u64 / 10_000;
-        let artist_cut = revenue.saturating_sub(venue_cut);
-        *self.balances_artist.entry(artist_id).or_default() += artist_cut;
-        *self.balances_venue.entry(venue_id).or_default() += venue_cut;
-        if let Some(c) = self.concerts.get_mut(&concert_id) {
-            c.cashed_out = true;
-        }
-        true
+    pub fn cash_out(&mut self, concert_id: ConcertId, now_ts: u64) -> Result<(), TicketingError> {
+        self.apply(Command::CashOut { concert_id, now_ts })?;
+        Ok(())
     }
 
     pub fn trade_ticket(
@@ -296,20 +1435,14 @@ impl Ticketing {
         seller: &str,
         buyer: &str,
         price: u64,
-    ) -> bool {
-        let ticket = match self.tickets.get_mut(&ticket_id) {
-            Some(t) => t,
-            None => return false,
-        };
-        if ticket.owner.as_deref() != Some(seller) || ticket.used {
-            return false;
-        }
-        if price > ticket.price_paid {
-            return false;
-        }
-        ticket.owner = Some(buyer.to_string());
-        ticket.price_paid = price;
-        true
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::TradeTicket {
+            ticket_id,
+            seller: seller.to_string(),
+            buyer: buyer.to_string(),
+            price,
+        })?;
+        Ok(())
     }
 
     pub fn distribute_ticket(
@@ -317,56 +1450,200 @@ impl Ticketing {
         concert_id: ConcertId,
         artist_id: ArtistId,
         redeem_code: &str,
-    ) -> Option<TicketId> {
-        let concert = self.concerts.get_mut(&concert_id)?;
-        if concert.artist_id != artist_id || !concert.validated_by_artist || !concert.validated_by_venue {
-            return None;
+    ) -> Result<TicketId, TicketingError> {
+        match self.apply(Command::DistributeTicket {
+            concert_id,
+            artist_id,
+            redeem_code: redeem_code.to_string(),
+        })? {
+            CommandOutcome::Ticket(id) => Ok(id),
+            _ => unreachable!("distribute_ticket yields a ticket on success"),
         }
-        if concert.tickets_issued >= concert.total_tickets {
-            return None;
+    }
+
+    pub fn redeem_ticket(&mut self, code: &str, user: &str) -> Result<TicketId, TicketingError> {
+        match self.apply(Command::RedeemTicket {
+            code: code.to_string(),
+            user: user.to_string(),
+        })? {
+            CommandOutcome::Ticket(id) => Ok(id),
+            _ => unreachable!("redeem_ticket yields a ticket on success"),
         }
-        concert.tickets_issued = concert.tickets_issued.saturating_add(1);
+    }
+
+    /// Contest a purchase: withhold its funds in escrow and flag the ticket
+    /// disputed, blocking use and resale until the dispute is settled. Only
+    /// real purchases are contestable; comp tickets have no sale to dispute.
+    pub fn dispute_purchase(&mut self, ticket_id: TicketId) -> Result<(), TicketingError> {
+        self.apply(Command::DisputePurchase { ticket_id })?;
+        Ok(())
+    }
 
-        self.next_ticket_id += 1;
-        let ticket_id = self.next_ticket_id;
-        self.tickets.insert(
+    /// Settle a dispute in the seller's favour: return the held funds to
+    /// available revenue and clear the flag. Must happen before `cash_out`,
+    /// after which the returned funds could no longer be paid out.
+    pub fn resolve_dispute(&mut self, ticket_id: TicketId) -> Result<(), TicketingError> {
+        self.apply(Command::ResolveDispute { ticket_id })?;
+        Ok(())
+    }
+
+    /// Settle a dispute in the buyer's favour: permanently remove the held
+    /// funds, void the ticket, and reverse the sale from the counters. A
+    /// dispute can only be opened before `cash_out`, so the contested funds
+    /// always sit in `held`; a chargeback after cash-out therefore reverses
+    /// them from escrow rather than being rejected.
+    pub fn chargeback(&mut self, ticket_id: TicketId) -> Result<(), TicketingError> {
+        self.apply(Command::Chargeback { ticket_id })?;
+        Ok(())
+    }
+
+    /// Commit to a lottery concert, holding `deposit` until the draw. The
+    /// deposit must cover the ticket price.
+    pub fn enter_lottery(
+        &mut self,
+        concert_id: ConcertId,
+        buyer: &str,
+        deposit: u64,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::EnterLottery {
+            concert_id,
+            buyer: buyer.to_string(),
+            deposit,
+        })?;
+        Ok(())
+    }
+
+    /// Run the seeded draw for a lottery concert, issuing tickets to the
+    /// winners and crediting every other deposit (and winner overpayment) to
+    /// the refund pool.
+    pub fn draw_lottery(&mut self, concert_id: ConcertId, seed: u64) -> Result<(), TicketingError> {
+        self.apply(Command::DrawLottery { concert_id, seed })?;
+        Ok(())
+    }
+
+    /// Withdraw a buyer's accumulated refunds, returning the amount swept.
+    pub fn claim_refund(&mut self, buyer: &str) -> Result<u64, TicketingError> {
+        match self.apply(Command::ClaimRefund {
+            buyer: buyer.to_string(),
+        })? {
+            CommandOutcome::Amount(amount) => Ok(amount),
+            _ => unreachable!("claim_refund yields an amount"),
+        }
+    }
+
+    pub fn refund_balance(&self, buyer: &str) -> u64 {
+        *self.refunds.get(buyer).unwrap_or(&0)
+    }
+
+    /// List a ticket for resale at `price`, capped at the price its seller
+    /// paid. Matching against resting bids runs immediately.
+    pub fn place_ask(
+        &mut self,
+        ticket_id: TicketId,
+        seller: &str,
+        price: u64,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::PlaceAsk {
             ticket_id,
-            Ticket {
-                id: ticket_id,
-                concert_id,
-                owner: None,
-                used: false,
-                price_paid: 0,
-                minted_by_artist: true,
-                redeem_code: Some(redeem_code.to_string()),
-            },
-        );
-        Some(ticket_id)
+            seller: seller.to_string(),
+            price,
+        })?;
+        Ok(())
     }
 
-    pub fn redeem_ticket(&mut self, code: &str, user: &str) -> Option<TicketId> {
-        let target = self
-            .tickets
-            .iter_mut()
-            .find(|(_, t)| t.owner.is_none() && t.redeem_code.as_deref() == Some(code));
-        if let Some((id, ticket)) = target {
-            ticket.owner = Some(user.to_string());
-            return Some(*id);
+    /// Place a standing bid for any ticket of `concert_id`, returning the bid
+    /// id (for [`cancel_bid`](Ticketing::cancel_bid)). Matching runs immediately.
+    pub fn place_bid(
+        &mut self,
+        concert_id: ConcertId,
+        buyer: &str,
+        price: u64,
+    ) -> Result<BidId, TicketingError> {
+        match self.apply(Command::PlaceBid {
+            concert_id,
+            buyer: buyer.to_string(),
+            price,
+        })? {
+            CommandOutcome::Bid(id) => Ok(id),
+            _ => unreachable!("place_bid yields a bid id"),
         }
-        None
+    }
+
+    /// Withdraw the resting ask for a ticket.
+    pub fn cancel_ask(&mut self, ticket_id: TicketId) -> Result<(), TicketingError> {
+        self.apply(Command::CancelAsk { ticket_id })?;
+        Ok(())
+    }
+
+    /// Withdraw a resting bid from a concert's book.
+    pub fn cancel_bid(
+        &mut self,
+        concert_id: ConcertId,
+        bid_id: BidId,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::CancelBid { concert_id, bid_id })?;
+        Ok(())
+    }
+
+    /// A read-only snapshot of a concert's resale book.
+    pub fn order_book(&self, concert_id: ConcertId) -> OrderBook {
+        self.books.get(&concert_id).cloned().unwrap_or_default()
     }
 
     pub fn ticket_owner(&self, ticket_id: TicketId) -> Option<String> {
         self.tickets.get(&ticket_id).and_then(|t| t.owner.clone())
     }
 
+    /// Set how long after the concert date cashed-out funds stay in escrow
+    /// before they can be claimed.
+    pub fn set_maturation_offset(
+        &mut self,
+        concert_id: ConcertId,
+        offset: u64,
+    ) -> Result<(), TicketingError> {
+        self.apply(Command::SetMaturationOffset { concert_id, offset })?;
+        Ok(())
+    }
+
+    /// Sweep every matured payout for a beneficiary into their spendable
+    /// balance, returning the total swept.
+    pub fn claim(&mut self, beneficiary: Beneficiary, now_ts: u64) -> u64 {
+        match self.apply(Command::Claim {
+            beneficiary,
+            now_ts,
+        }) {
+            Ok(CommandOutcome::Amount(total)) => total,
+            _ => 0,
+        }
+    }
+
+    /// Spendable (already claimed) artist balance.
     pub fn balance_artist(&self, artist_id: ArtistId) -> u64 {
         *self.balances_artist.get(&artist_id).unwrap_or(&0)
     }
 
+    /// Spendable (already claimed) venue balance.
     pub fn balance_venue(&self, venue_id: VenueId) -> u64 {
         *self.balances_venue.get(&venue_id).unwrap_or(&0)
     }
+
+    /// Escrowed artist funds not yet claimed.
+    pub fn pending_artist(&self, artist_id: ArtistId) -> u64 {
+        self.pending
+            .iter()
+            .filter(|p| p.beneficiary == Beneficiary::Artist(artist_id))
+            .map(|p| p.amount)
+            .sum()
+    }
+
+    /// Escrowed venue funds not yet claimed.
+    pub fn pending_venue(&self, venue_id: VenueId) -> u64 {
+        self.pending
+            .iter()
+            .filter(|p| p.beneficiary == Beneficiary::Venue(venue_id))
+            .map(|p| p.amount)
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -386,45 +1663,361 @@ mod tests {
     #[test]
     fn enforces_supply_across_sales_and_distributions() {
         let (mut t, concert, artist, _) = setup_validated_concert(2);
-        assert!(t.buy_ticket(concert, "alice", 100).is_some());
-        assert!(t.distribute_ticket(concert, artist, "FREE1").is_some());
-        assert!(t.emit_ticket(concert, artist, None).is_none(), "supply cap must block extra tickets");
-        assert!(t.buy_ticket(concert, "bob", 100).is_none(), "supply cap must block further sales");
+        assert!(t.buy_ticket(concert, "alice", 100).is_ok());
+        assert!(t.distribute_ticket(concert, artist, "FREE1").is_ok());
+        assert_eq!(
+            t.emit_ticket(concert, artist, None),
+            Err(TicketingError::SupplyExhausted),
+            "supply cap must block extra tickets"
+        );
+        assert_eq!(
+            t.buy_ticket(concert, "bob", 100),
+            Err(TicketingError::SupplyExhausted),
+            "supply cap must block further sales"
+        );
     }
 
     #[test]
     fn redeem_distributed_ticket() {
         let (mut t, concert, artist, _) = setup_validated_concert(1);
         let ticket_id = t.distribute_ticket(concert, artist, "CODE123").expect("ticket minted");
-        assert_eq!(t.redeem_ticket("CODE123", "carol"), Some(ticket_id));
+        assert_eq!(t.redeem_ticket("CODE123", "carol"), Ok(ticket_id));
         assert_eq!(t.ticket_owner(ticket_id).as_deref(), Some("carol"));
-        assert_eq!(t.redeem_ticket("CODE123", "dave"), None, "code unusable after redeem");
+        assert_eq!(
+            t.redeem_ticket("CODE123", "dave"),
+            Err(TicketingError::RedeemCodeNotFound),
+            "code unusable after redeem"
+        );
     }
 
     #[test]
     fn use_ticket_respects_window_and_validation() {
         let (mut t, concert, _, _) = setup_validated_concert(2);
         let early_ticket = t.buy_ticket(concert, "eve", 100).unwrap();
-        assert!(!t.use_ticket(early_ticket, "eve", 1_000_000 - 86_401));
-        assert!(t.use_ticket(early_ticket, "eve", 1_000_000 - 10));
-        assert!(!t.use_ticket(early_ticket, "eve", 1_000_000 - 5), "cannot reuse");
+        assert_eq!(
+            t.use_ticket(early_ticket, "eve", 1_000_000 - 86_401),
+            Err(TicketingError::OutsideUsageWindow)
+        );
+        assert!(t.use_ticket(early_ticket, "eve", 1_000_000 - 10).is_ok());
+        assert_eq!(
+            t.use_ticket(early_ticket, "eve", 1_000_000 - 5),
+            Err(TicketingError::AlreadyUsed),
+            "cannot reuse"
+        );
 
         // After event, usage should fail
         let late_ticket = t.buy_ticket(concert, "frank", 100).unwrap();
-        assert!(!t.use_ticket(late_ticket, "frank", 1_000_000 + 1));
+        assert_eq!(
+            t.use_ticket(late_ticket, "frank", 1_000_000 + 1),
+            Err(TicketingError::OutsideUsageWindow)
+        );
     }
 
     #[test]
     fn trade_ticket_never_above_purchase_price() {
         let (mut t, concert, artist, _) = setup_validated_concert(3);
         let paid_ticket = t.buy_ticket(concert, "gina", 100).unwrap();
-        assert!(!t.trade_ticket(paid_ticket, "gina", "helen", 120), "cannot sell above paid price");
-        assert!(t.trade_ticket(paid_ticket, "gina", "helen", 80));
+        assert_eq!(
+            t.trade_ticket(paid_ticket, "gina", "helen", 120),
+            Err(TicketingError::PriceAboveFaceValue),
+            "cannot sell above paid price"
+        );
+        assert!(t.trade_ticket(paid_ticket, "gina", "helen", 80).is_ok());
 
         // Free ticket cannot be resold for profit
         let free_ticket = t.emit_ticket(concert, artist, None).unwrap();
-        assert!(!t.trade_ticket(free_ticket, &format!("artist:{artist}"), "ian", 10));
-        assert!(t.trade_ticket(free_ticket, &format!("artist:{artist}"), "ian", 0));
+        assert!(t
+            .trade_ticket(free_ticket, &format!("artist:{artist}"), "ian", 10)
+            .is_err());
+        assert!(t
+            .trade_ticket(free_ticket, &format!("artist:{artist}"), "ian", 0)
+            .is_ok());
+    }
+
+    #[test]
+    fn cash_out_split_is_exact_and_overflow_safe() {
+        let mut t = Ticketing::default();
+        let artist = t.create_artist("Artist", "band");
+        // 25% venue cut, and a revenue large enough that `revenue * bps` would
+        // overflow u64 without the u128 widening.
+        let venue = t.create_venue("Venue", u32::MAX, 2_500, None);
+        let concert = t.create_concert(artist, venue, 0, u64::MAX, 1);
+        t.validate_concert_by_artist(concert, artist);
+        t.validate_concert_by_venue(concert, venue);
+        t.buy_ticket(concert, "alice", u64::MAX).unwrap();
+
+        assert!(t.cash_out(concert, 0).is_ok());
+        // Funds are escrowed until claimed.
+        t.claim(Beneficiary::Artist(artist), 0);
+        t.claim(Beneficiary::Venue(venue), 0);
+        assert_eq!(
+            t.balance_artist(artist) + t.balance_venue(venue),
+            u64::MAX,
+            "the split must conserve the full revenue exactly"
+        );
+        assert_eq!(
+            t.cash_out(concert, 0),
+            Err(TicketingError::AlreadyCashedOut)
+        );
+    }
+
+    #[test]
+    fn dispute_withholds_funds_and_chargeback_voids_ticket() {
+        let (mut t, concert, artist, venue) = setup_validated_concert(3);
+        let ticket = t.buy_ticket(concert, "alice", 100).unwrap();
+        t.buy_ticket(concert, "bob", 100).unwrap();
+
+        // A dispute moves the contested funds out of reach of cash-out.
+        t.dispute_purchase(ticket).unwrap();
+        assert_eq!(
+            t.dispute_purchase(ticket),
+            Err(TicketingError::AlreadyDisputed)
+        );
+        assert!(t.use_ticket(ticket, "alice", 1_000_000).is_err(), "disputed ticket cannot be used");
+        assert!(t.trade_ticket(ticket, "alice", "carol", 50).is_err(), "disputed ticket cannot be resold");
+
+        // Resolving returns the funds; cashing out then pays on full revenue.
+        t.resolve_dispute(ticket).unwrap();
+        assert!(t.cash_out(concert, 1_000_000).is_ok());
+        t.claim(Beneficiary::Artist(artist), 1_000_000);
+        t.claim(Beneficiary::Venue(venue), 1_000_000);
+        assert_eq!(t.balance_artist(artist) + t.balance_venue(venue), 200);
+    }
+
+    #[test]
+    fn chargeback_reverses_the_sale() {
+        let (mut t, concert, artist, venue) = setup_validated_concert(2);
+        let ticket = t.buy_ticket(concert, "alice", 100).unwrap();
+        t.dispute_purchase(ticket).unwrap();
+        t.chargeback(ticket).unwrap();
+
+        assert_eq!(t.artists[&artist].total_tickets_sold, 0);
+        assert_eq!(t.concerts[&concert].tickets_sold, 0);
+        assert_eq!(
+            t.dispute_purchase(ticket),
+            Err(TicketingError::TicketVoid),
+            "a void ticket cannot be disputed again"
+        );
+
+        // Charged-back funds never reach the payout balances.
+        assert!(t.cash_out(concert, 1_000_000).is_ok());
+        assert_eq!(t.balance_artist(artist) + t.balance_venue(venue), 0);
+    }
+
+    #[test]
+    fn comp_tickets_cannot_be_disputed() {
+        let (mut t, concert, artist, _) = setup_validated_concert(2);
+        let comp = t.emit_ticket(concert, artist, None).unwrap();
+        assert_eq!(
+            t.dispute_purchase(comp),
+            Err(TicketingError::NotPurchased),
+            "a comp was never sold, so it has no purchase to dispute"
+        );
+        // The aggregate sold counters stay untouched.
+        assert_eq!(t.artists[&artist].total_tickets_sold, 0);
+        assert_eq!(t.concerts[&concert].tickets_sold, 0);
+    }
+
+    #[test]
+    fn chargeback_after_cash_out_reverses_held_funds() {
+        let (mut t, concert, artist, venue) = setup_validated_concert(2);
+        let ticket = t.buy_ticket(concert, "alice", 100).unwrap();
+        t.dispute_purchase(ticket).unwrap();
+        // Cash-out succeeds on the remaining (non-held) revenue.
+        assert!(t.cash_out(concert, 1_000_000).is_ok());
+        // The disputed funds stayed in `held`, never disbursed, so a later
+        // chargeback reverses them from there rather than being rejected.
+        t.chargeback(ticket).unwrap();
+        assert_eq!(t.concerts[&concert].held, 0);
+        assert_eq!(t.balance_artist(artist) + t.balance_venue(venue), 0);
+    }
+
+    fn setup_lottery_concert(supply: u32) -> (Ticketing, ConcertId, ArtistId, VenueId) {
+        let mut t = Ticketing::default();
+        let artist = t.create_artist("Artist", "band");
+        let venue = t.create_venue("Venue", 1_000, 1_000, None);
+        let concert = t.create_concert_with_allocation(
+            artist,
+            venue,
+            1_000_000,
+            100,
+            supply,
+            AllocationMode::Lottery,
+        );
+        t.validate_concert_by_artist(concert, artist);
+        t.validate_concert_by_venue(concert, venue);
+        (t, concert, artist, venue)
+    }
+
+    #[test]
+    fn oversubscribed_lottery_allocates_supply_and_conserves_deposits() {
+        let (mut t, concert, _, _) = setup_lottery_concert(2);
+        assert_eq!(
+            t.buy_ticket(concert, "x", 100),
+            Err(TicketingError::LotteryConcert),
+            "lottery concerts reject direct buys"
+        );
+        assert_eq!(
+            t.enter_lottery(concert, "poor", 50),
+            Err(TicketingError::DepositBelowPrice)
+        );
+
+        let bidders = ["alice", "bob", "carol", "dave"];
+        for b in bidders {
+            t.enter_lottery(concert, b, 150).unwrap();
+        }
+        t.draw_lottery(concert, 42).unwrap();
+        assert_eq!(
+            t.draw_lottery(concert, 42),
+            Err(TicketingError::LotteryAlreadyDrawn)
+        );
+
+        assert_eq!(t.concerts[&concert].tickets_sold, 2, "exactly supply issued");
+        let total_refunds: u64 = bidders.iter().map(|b| t.refund_balance(b)).sum();
+        assert_eq!(
+            t.concerts[&concert].revenue + total_refunds,
+            4 * 150,
+            "deposits conserved across revenue and refunds"
+        );
+
+        for b in bidders {
+            let owed = t.refund_balance(b);
+            assert_eq!(t.claim_refund(b).unwrap(), owed);
+            assert_eq!(t.refund_balance(b), 0, "claim empties the balance");
+        }
+    }
+
+    #[test]
+    fn lottery_concerts_reject_comps_and_never_exceed_capacity() {
+        let (mut t, concert, artist, _) = setup_lottery_concert(2);
+        // Lottery concerts hand out tickets only through the draw.
+        assert_eq!(
+            t.emit_ticket(concert, artist, None),
+            Err(TicketingError::LotteryConcert)
+        );
+        assert_eq!(
+            t.distribute_ticket(concert, artist, "FREE"),
+            Err(TicketingError::LotteryConcert)
+        );
+
+        for b in ["alice", "bob", "carol"] {
+            t.enter_lottery(concert, b, 120).unwrap();
+        }
+        t.draw_lottery(concert, 1).unwrap();
+        assert_eq!(t.concerts[&concert].tickets_issued, 2, "never over capacity");
+    }
+
+    #[test]
+    fn undersubscribed_lottery_issues_to_everyone() {
+        let (mut t, concert, _, _) = setup_lottery_concert(5);
+        for b in ["alice", "bob", "carol"] {
+            t.enter_lottery(concert, b, 120).unwrap();
+        }
+        t.draw_lottery(concert, 7).unwrap();
+        assert_eq!(t.concerts[&concert].tickets_sold, 3, "all entries win");
+        // Each winner overpaid 20 and gets it back; nobody is a full-deposit loser.
+        for b in ["alice", "bob", "carol"] {
+            assert_eq!(t.refund_balance(b), 20);
+        }
+    }
+
+    #[test]
+    fn order_book_matches_at_ask_price_and_caps_resale() {
+        let (mut t, concert, _, _) = setup_validated_concert(3);
+        let ticket = t.buy_ticket(concert, "alice", 100).unwrap();
+        assert_eq!(
+            t.place_ask(ticket, "alice", 120),
+            Err(TicketingError::PriceAboveFaceValue),
+            "cannot list above price paid"
+        );
+
+        // A resting ask with no crossing bid simply waits.
+        t.place_ask(ticket, "alice", 80).unwrap();
+        assert_eq!(t.order_book(concert).asks.len(), 1);
+
+        // A bid at or above the ask clears immediately at the ask price.
+        t.place_bid(concert, "bob", 90).unwrap();
+        assert_eq!(t.ticket_owner(ticket).as_deref(), Some("bob"));
+        assert_eq!(t.tickets[&ticket].price_paid, 80, "settles at the ask price");
+        let book = t.order_book(concert);
+        assert!(book.asks.is_empty() && book.bids.is_empty(), "both sides consumed");
+    }
+
+    #[test]
+    fn order_book_rests_non_crossing_orders_and_cancels() {
+        let (mut t, concert, _, _) = setup_validated_concert(3);
+        let ticket = t.buy_ticket(concert, "alice", 100).unwrap();
+        t.place_ask(ticket, "alice", 90).unwrap();
+        let bid = t.place_bid(concert, "bob", 50).unwrap();
+        assert_eq!(t.ticket_owner(ticket).as_deref(), Some("alice"), "spread does not cross");
+        assert_eq!(t.order_book(concert).asks.len(), 1);
+        assert_eq!(t.order_book(concert).bids.len(), 1);
+
+        t.cancel_ask(ticket).unwrap();
+        t.cancel_bid(concert, bid).unwrap();
+        assert!(t.order_book(concert).asks.is_empty());
+        assert!(t.order_book(concert).bids.is_empty());
+        assert_eq!(t.cancel_ask(ticket), Err(TicketingError::OrderNotFound));
+    }
+
+    #[test]
+    fn payouts_mature_before_they_can_be_claimed() {
+        let (mut t, concert, artist, venue) = setup_validated_concert(3);
+        t.set_maturation_offset(concert, 100).unwrap();
+        t.buy_ticket(concert, "alice", 100).unwrap();
+        t.buy_ticket(concert, "bob", 100).unwrap();
+        assert!(t.cash_out(concert, 1_000_000).is_ok());
+
+        // Nothing settles at cash-out; the split waits in escrow.
+        assert_eq!(t.balance_artist(artist), 0);
+        assert_eq!(t.pending_artist(artist), 180);
+        assert_eq!(t.pending_venue(venue), 20);
+
+        // A claim before maturation sweeps nothing.
+        assert_eq!(t.claim(Beneficiary::Artist(artist), 1_000_050), 0);
+        assert_eq!(t.balance_artist(artist), 0);
+
+        // After maturation the payout settles and the pending entry clears.
+        assert_eq!(t.claim(Beneficiary::Artist(artist), 1_000_100), 180);
+        assert_eq!(t.balance_artist(artist), 180);
+        assert_eq!(t.pending_artist(artist), 0);
+    }
+
+    #[test]
+    fn chargeback_before_maturation_leaves_other_payouts_intact() {
+        // 10% venue cut; alice is a legitimate sale, bob disputes before
+        // cash-out so only alice's 100 is escrowed (90 artist + 10 venue).
+        let (mut t, concert, artist, venue) = setup_validated_concert(3);
+        t.set_maturation_offset(concert, 100).unwrap();
+        t.buy_ticket(concert, "alice", 100).unwrap();
+        let bob_ticket = t.buy_ticket(concert, "bob", 100).unwrap();
+        t.dispute_purchase(bob_ticket).unwrap();
+        assert!(t.cash_out(concert, 1_000_000).is_ok());
+        assert_eq!(t.pending_artist(artist), 90);
+        assert_eq!(t.pending_venue(venue), 10);
+
+        // Bob's chargeback reverses only his own (held) funds; alice's
+        // escrowed payout must survive and still mature into a claim.
+        t.chargeback(bob_ticket).unwrap();
+        assert_eq!(t.pending_artist(artist), 90);
+        assert_eq!(t.pending_venue(venue), 10);
+        assert_eq!(t.claim(Beneficiary::Artist(artist), 1_000_100), 90);
+        assert_eq!(t.claim(Beneficiary::Venue(venue), 1_000_100), 10);
+    }
+
+    #[test]
+    fn replay_reconstructs_identical_state() {
+        let (t, concert, artist, _) = setup_validated_concert(3);
+        let mut t = t;
+        let bought = t.buy_ticket(concert, "jade", 100).unwrap();
+        t.distribute_ticket(concert, artist, "CODE").unwrap();
+        t.redeem_ticket("CODE", "kyle").unwrap();
+
+        let replayed = Ticketing::replay(t.command_log().iter().cloned());
+        assert_eq!(replayed.ticket_owner(bought).as_deref(), Some("jade"));
+        assert_eq!(replayed.command_log().len(), t.command_log().len());
+        assert_eq!(replayed.next_ticket_id, t.next_ticket_id);
+        assert_eq!(replayed.next_concert_id, t.next_concert_id);
     }
 }
 